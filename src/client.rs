@@ -0,0 +1,264 @@
+use crate::executor;
+use crate::net::TcpStream;
+use crate::reactor::Reactor;
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use hyper::body::HttpBody;
+use hyper::client::conn::{self, SendRequest};
+use hyper::http::{HeaderMap, Method, Version};
+use hyper::{Body, Request, Response, Uri};
+
+/// A blocking HTTP/1 client that reuses astra's [`Reactor`] and worker pool,
+/// so a [`Service`](crate::Service) can make outbound requests (proxying,
+/// service-to-service calls, webhooks) without spawning a separate async
+/// runtime just for the client side.
+///
+/// Connections are pooled per host/port and reused across calls to
+/// [`request`](Client::request). Only plain `http` is supported, so the
+/// pool key doesn't need to carry a scheme; before reusing a pooled
+/// connection its liveness is checked (and, if it's dead, it's dropped and
+/// a fresh one is made instead), and a request whose body is empty is
+/// retried once, with a new checkout (another live pooled connection or a
+/// newly made one), if hyper reports it as canceled before it was sent over
+/// a connection that looked live. Other send errors are not retried.
+#[derive(Clone)]
+pub struct Client {
+    reactor: Reactor,
+    pool: Arc<Mutex<HashMap<Key, Vec<SendRequest<Body>>>>>,
+}
+
+type Key = (String, u16);
+
+/// The parts of a request needed to rebuild an equivalent, empty-bodied
+/// request for the one-shot retry in [`Client::request`].
+struct RequestHead {
+    method: Method,
+    uri: Uri,
+    version: Version,
+    headers: HeaderMap,
+}
+
+enum SendError {
+    Connect(io::Error),
+    Send(hyper::Error),
+}
+
+impl From<SendError> for io::Error {
+    fn from(err: SendError) -> io::Error {
+        match err {
+            SendError::Connect(err) => err,
+            SendError::Send(err) => io::Error::new(io::ErrorKind::Other, err),
+        }
+    }
+}
+
+impl Client {
+    /// Creates a client driven by `reactor`.
+    ///
+    /// Sharing the [`Reactor`] a [`Server`](crate::Server) was bound with
+    /// lets a handler call out and block without a second runtime.
+    pub fn new(reactor: Reactor) -> Client {
+        Client {
+            reactor,
+            pool: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sends `req` and blocks the calling thread until the response headers
+    /// arrive.
+    ///
+    /// `req`'s URI must be absolute (carry a host); only plain `http` is
+    /// currently supported.
+    pub fn request(&self, req: Request<Body>) -> io::Result<Response<Body>> {
+        let key = key_for(req.uri())?;
+
+        // A request with no body can be safely replayed through a new
+        // checkout if this attempt is canceled: hyper hasn't written anything
+        // of it to the wire yet either way. Anything else, we only get one
+        // shot at, since `send_request` doesn't hand the request back on
+        // failure.
+        let retry_head = req.body().is_end_stream().then(|| head_of(&req));
+
+        match self.try_send(&key, req) {
+            Ok(res) => Ok(res),
+            Err(SendError::Send(err)) if err.is_canceled() => match retry_head {
+                Some(head) => self
+                    .try_send(&key, into_request(head))
+                    .map_err(io::Error::from),
+                None => Err(io::Error::from(SendError::Send(err))),
+            },
+            Err(err) => Err(io::Error::from(err)),
+        }
+    }
+
+    fn try_send(&self, key: &Key, req: Request<Body>) -> Result<Response<Body>, SendError> {
+        let mut sender = self.checkout(key).map_err(SendError::Connect)?;
+
+        match executor::block_on(sender.send_request(req)) {
+            Ok(res) => {
+                self.put_pooled(key.clone(), sender);
+                Ok(res)
+            }
+            Err(err) => Err(SendError::Send(err)),
+        }
+    }
+
+    /// Returns a live connection for `key`: a pooled one that's still
+    /// usable, or a freshly connected one.
+    ///
+    /// Pooled connections the peer has since closed (by far the most
+    /// common way a keep-alive pool goes stale) are detected here, via
+    /// `SendRequest::ready`, and dropped rather than handed back to the
+    /// caller.
+    fn checkout(&self, key: &Key) -> io::Result<SendRequest<Body>> {
+        while let Some(mut sender) = self.take_pooled(key) {
+            if executor::block_on(sender.ready()).is_ok() {
+                return Ok(sender);
+            }
+        }
+
+        self.connect(key)
+    }
+
+    fn take_pooled(&self, key: &Key) -> Option<SendRequest<Body>> {
+        self.pool.lock().unwrap().get_mut(key).and_then(Vec::pop)
+    }
+
+    fn put_pooled(&self, key: Key, sender: SendRequest<Body>) {
+        self.pool
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .push(sender);
+    }
+
+    fn connect(&self, key: &Key) -> io::Result<SendRequest<Body>> {
+        let stream = TcpStream::connect(self.reactor.clone(), (key.0.as_str(), key.1))?;
+
+        let (sender, connection) = executor::block_on(conn::handshake(stream))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        // Drives the connection's I/O on the pool; dropping `sender` (once
+        // evicted from the pool on a dead `ready()` check) lets this exit.
+        executor::spawn(async move {
+            let _ = connection.await;
+        });
+
+        Ok(sender)
+    }
+}
+
+fn head_of(req: &Request<Body>) -> RequestHead {
+    RequestHead {
+        method: req.method().clone(),
+        uri: req.uri().clone(),
+        version: req.version(),
+        headers: req.headers().clone(),
+    }
+}
+
+fn into_request(head: RequestHead) -> Request<Body> {
+    let mut req = Request::new(Body::empty());
+    *req.method_mut() = head.method;
+    *req.uri_mut() = head.uri;
+    *req.version_mut() = head.version;
+    *req.headers_mut() = head.headers;
+    req
+}
+
+fn key_for(uri: &Uri) -> io::Result<Key> {
+    match uri.scheme_str() {
+        Some("http") | None => {}
+        Some(scheme) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported URI scheme {scheme:?}; Client only supports http"),
+            ))
+        }
+    }
+
+    let host = uri
+        .host()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "request URI has no host"))?
+        .to_owned();
+
+    let port = uri.port_u16().unwrap_or(80);
+
+    Ok((host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_for_rejects_non_http_schemes() {
+        let uri: Uri = "https://example.com".parse().unwrap();
+        let err = key_for(&uri).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn key_for_defaults_to_port_80() {
+        let uri: Uri = "http://example.com/path".parse().unwrap();
+        assert_eq!(key_for(&uri).unwrap(), ("example.com".to_owned(), 80));
+    }
+
+    #[test]
+    fn key_for_honors_explicit_port() {
+        let uri: Uri = "http://example.com:9000".parse().unwrap();
+        assert_eq!(key_for(&uri).unwrap(), ("example.com".to_owned(), 9000));
+    }
+
+    #[test]
+    fn key_for_requires_a_host() {
+        let uri: Uri = "/just-a-path".parse().unwrap();
+        assert!(key_for(&uri).is_err());
+    }
+
+    /// A pooled connection the peer closes between requests should be
+    /// evicted by `checkout`'s liveness check, not handed back to the
+    /// caller as a failure.
+    #[test]
+    fn request_reconnects_after_peer_closes_pooled_connection() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::thread;
+        use std::time::Duration;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for _ in 0..2 {
+                if let Ok((mut stream, _)) = listener.accept() {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf);
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+                    // Dropping `stream` here closes the connection right
+                    // after the response, so the pooled `SendRequest` is
+                    // dead by the time the next request checks it out.
+                }
+            }
+        });
+
+        let client = Client::new(Reactor::new().unwrap());
+        let uri: Uri = format!("http://{addr}").parse().unwrap();
+
+        let get = || Request::get(uri.clone()).body(Body::empty()).unwrap();
+
+        let res = client.request(get()).unwrap();
+        assert_eq!(res.status(), hyper::StatusCode::OK);
+
+        // Give the spawned connection-driving task a moment to notice the
+        // peer closed the socket before the next checkout.
+        thread::sleep(Duration::from_millis(100));
+
+        let res = client.request(get()).unwrap();
+        assert_eq!(res.status(), hyper::StatusCode::OK);
+    }
+}
@@ -1,14 +1,17 @@
 use crate::executor;
-use crate::net::TcpListener;
+use crate::net::{TcpListener, TcpStream};
 use crate::reactor::Reactor;
 
 use std::convert::Infallible;
 use std::future::{ready, Future, Ready};
-use std::io;
+use std::io::{self, Read, Write};
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::task::{Context, Poll};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
 
 use hyper::{Request, Response};
@@ -33,6 +36,8 @@ pub struct Server {
     http2_max_send_buf_size: Option<usize>,
     worker_keep_alive: Option<Duration>,
     max_workers: Option<usize>,
+    http1_header_read_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
 }
 
 pub trait Service: Send + Sync + 'static {
@@ -48,7 +53,240 @@ where
     }
 }
 
+/// Like [`Service`], but also receives the [`ConnInfo`] of the connection
+/// the request arrived on.
+///
+/// Every [`Service`] implements `ConnService` already (ignoring the info),
+/// so existing handlers keep working unchanged; implement this directly
+/// only when you need to know who connected.
+pub trait ConnService: Send + Sync + 'static {
+    fn call(&self, request: Request<hyper::Body>, info: &ConnInfo) -> Response<hyper::Body>;
+}
+
+impl<S> ConnService for S
+where
+    S: Service,
+{
+    fn call(&self, request: Request<hyper::Body>, _info: &ConnInfo) -> Response<hyper::Body> {
+        Service::call(self, request)
+    }
+}
+
+/// Metadata about the connection a request arrived on, passed to
+/// [`ConnService::call`].
+#[derive(Clone, Debug)]
+pub struct ConnInfo {
+    remote_addr: SocketAddr,
+    local_addr: Option<SocketAddr>,
+}
+
+impl ConnInfo {
+    fn new(remote_addr: SocketAddr, local_addr: Option<SocketAddr>) -> Self {
+        ConnInfo {
+            remote_addr,
+            local_addr,
+        }
+    }
+
+    /// The address of the connected client.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// The local address the connection was accepted on, when known.
+    ///
+    /// This is only available for connections accepted through
+    /// [`Server::serve`]/[`Server::serve_with_shutdown`]; acceptors plugged
+    /// in through [`Server::serve_incoming`] don't currently report one.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+}
+
+/// Implemented by the connection types hyper hands to [`MakeService`], so
+/// its [`remote_addr`](ConnAddr::remote_addr) (and, when available,
+/// [`local_addr`](ConnAddr::local_addr)) can be captured into a
+/// [`ConnInfo`] before the connection starts serving requests.
+trait ConnAddr {
+    fn remote_addr(&self) -> SocketAddr;
+    fn local_addr(&self) -> Option<SocketAddr>;
+}
+
+impl ConnAddr for TcpStream {
+    fn remote_addr(&self) -> SocketAddr {
+        TcpStream::remote_addr(self)
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpStream::local_addr(self).ok()
+    }
+}
+
+/// A handle for gracefully shutting down a [`Server`] started with
+/// [`serve_with_shutdown`](Server::serve_with_shutdown).
+///
+/// Cloning a `Handle` is cheap, and every clone controls the same server,
+/// so it can be stashed away (e.g. in a signal handler or another thread)
+/// ahead of the call to `serve_with_shutdown`.
+///
+/// Shutting down doesn't just wake the task awaiting the handle as a
+/// future: it also writes a byte down a self-pipe whose read end
+/// [`TcpListener`]'s accept loop registers with the [`Reactor`] alongside
+/// the listening socket (see [`signal`](Handle::signal)). That's what lets
+/// `shutdown` interrupt a blocking `accept()` call that's parked waiting
+/// for the next connection, rather than only taking effect once one
+/// happens to arrive.
+#[derive(Clone)]
+pub struct Handle {
+    shutdown: Arc<AtomicBool>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    notify: Arc<UnixStream>,
+    wakeup: Arc<UnixStream>,
+}
+
+impl Default for Handle {
+    fn default() -> Self {
+        Handle::new()
+    }
+}
+
+impl Handle {
+    /// Creates a new `Handle` for a server that hasn't been shut down yet.
+    pub fn new() -> Handle {
+        let (notify, wakeup) = UnixStream::pair().expect("failed to create shutdown pipe");
+        wakeup
+            .set_nonblocking(true)
+            .expect("failed to configure shutdown pipe");
+
+        Handle {
+            shutdown: Arc::new(AtomicBool::new(false)),
+            waker: Arc::new(Mutex::new(None)),
+            notify: Arc::new(notify),
+            wakeup: Arc::new(wakeup),
+        }
+    }
+
+    /// Signals the associated server to stop accepting new connections and
+    /// begin a graceful shutdown.
+    ///
+    /// This returns immediately; it's `serve_with_shutdown` that returns once
+    /// every in-flight connection has finished (or, if a grace period was
+    /// set, once it elapses).
+    ///
+    /// Calling this more than once has no additional effect.
+    pub fn shutdown(&self) {
+        if self.shutdown.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+
+        // Best-effort: wakes up `TcpListener`'s blocking `accept()` loop. If
+        // the pipe is full or already broken there's nothing useful to do
+        // with the error, and the flag above is still observed on the next
+        // wakeup regardless.
+        let _ = (&*self.notify).write(&[0]);
+    }
+
+    /// The [`Reactor`]-registerable side of this handle's self-pipe, handed
+    /// to [`TcpListener::bind`] so its accept loop can watch it alongside
+    /// the listening socket.
+    pub(crate) fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            shutdown: self.shutdown.clone(),
+            wakeup: self.wakeup.clone(),
+        }
+    }
+}
+
+/// The read end of a [`Handle`]'s self-pipe, plus the flag it's paired
+/// with. `TcpListener`'s accept loop registers [`as_raw_fd`](AsRawFd) with
+/// the [`Reactor`] next to the listening socket's fd, so `epoll_wait`
+/// returns as soon as either is readable; [`is_shutdown`](Self::is_shutdown)
+/// then distinguishes "a connection arrived" from "time to stop".
+pub(crate) struct ShutdownSignal {
+    shutdown: Arc<AtomicBool>,
+    wakeup: Arc<UnixStream>,
+}
+
+impl ShutdownSignal {
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::Acquire)
+    }
+}
+
+impl AsRawFd for ShutdownSignal {
+    fn as_raw_fd(&self) -> RawFd {
+        self.wakeup.as_raw_fd()
+    }
+}
+
+/// A source of incoming connections for a [`Server`].
+///
+/// This mirrors hyper's own `Accept` trait, except `accept` is a plain
+/// blocking call: implementors are expected to block the calling worker
+/// thread until a connection (or an error) is available, the same way
+/// [`TcpListener`] already does internally.
+///
+/// Implement this to plug in listeners astra doesn't provide out of the
+/// box, such as Unix domain sockets, or to terminate TLS by wrapping each
+/// accepted [`Read`] + [`Write`] stream (e.g. in a `rustls`
+/// `StreamOwned`) before handing it to [`Server::serve_incoming`].
+pub trait Accept {
+    /// The connection type produced by this acceptor.
+    type Conn: Read + Write + Send + 'static;
+
+    /// Blocks until the next connection is accepted, returning `Ok(None)`
+    /// once the acceptor has been closed and no further connections will
+    /// arrive.
+    fn accept(&self) -> io::Result<Option<(Self::Conn, SocketAddr)>>;
+}
+
+impl Accept for TcpListener {
+    type Conn = TcpStream;
+
+    fn accept(&self) -> io::Result<Option<(TcpStream, SocketAddr)>> {
+        TcpListener::accept(self)
+    }
+}
+
+impl Future for Handle {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.shutdown.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+        // Re-check after registering the waker, in case `shutdown` ran
+        // between the first check and the lock above.
+        if self.shutdown.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
 impl Server {
+    /// Creates a `Server` for configuring HTTP options without binding an
+    /// address.
+    ///
+    /// This is for [`serve_connection`](Server::serve_connection), which
+    /// only needs the HTTP option builder methods and doesn't bind or
+    /// resolve anything itself. [`serve_incoming`](Server::serve_incoming)
+    /// takes its connections from an acceptor and doesn't need an address
+    /// either, while [`serve`](Server::serve) and
+    /// [`serve_with_shutdown`](Server::serve_with_shutdown) still require
+    /// [`bind`](Server::bind).
+    pub fn new() -> Server {
+        Server::default()
+    }
+
     /// Binds to the provided address, and returns a [`Builder`](Builder).
     ///
     /// # Panics
@@ -62,12 +300,19 @@ impl Server {
     }
     pub fn serve<S>(self, service: S) -> io::Result<()>
     where
-        S: Service,
+        S: ConnService,
     {
         let reactor = Reactor::new().expect("failed to create reactor");
 
-        let listener = TcpListener::bind(reactor, self.addr.unwrap().as_slice())
-            .expect("failed to bind listener");
+        let listener = TcpListener::bind(
+            reactor,
+            self.addr
+                .expect("Server::serve requires an address; construct with Server::bind, not Server::new")
+                .as_slice(),
+            self.idle_timeout,
+            None,
+        )
+        .expect("failed to bind listener");
 
         let executor = executor::Executor::new(self.max_workers, self.worker_keep_alive);
         let builder = hyper::Server::builder(listener).executor(executor);
@@ -90,7 +335,8 @@ impl Server {
                 http2_adaptive_window,
                 http2_max_frame_size,
                 http2_max_concurrent_streams,
-                http2_max_send_buf_size
+                http2_max_send_buf_size,
+                http1_header_read_timeout
             ]
         );
 
@@ -98,6 +344,196 @@ impl Server {
         executor::block_on(server).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
     }
 
+    /// Like [`serve`](Server::serve), but accepts connections from an
+    /// arbitrary [`Accept`] implementor instead of binding a [`TcpListener`]
+    /// directly.
+    ///
+    /// This is how astra supports things it doesn't implement itself, such
+    /// as TLS termination or Unix domain sockets: implement [`Accept`] for
+    /// your listener, doing any blocking handshake inside `accept` on the
+    /// worker thread, and hand it to this method instead of calling
+    /// [`bind`](Server::bind)/[`serve`](Server::serve).
+    pub fn serve_incoming<A, S>(self, acceptor: A, service: S) -> io::Result<()>
+    where
+        A: Accept + Send + Sync + 'static,
+        A::Conn: AsRawFd,
+        S: ConnService,
+    {
+        let reactor = Reactor::new().expect("failed to create reactor");
+
+        let executor = executor::Executor::new(self.max_workers, self.worker_keep_alive);
+        let builder = hyper::Server::builder(incoming::Incoming::new(
+            reactor,
+            acceptor,
+            self.idle_timeout,
+        ))
+        .executor(executor);
+
+        let builder = options!(
+            self,
+            builder,
+            [
+                http1_keepalive,
+                http1_half_close,
+                http1_max_buf_size,
+                http1_pipeline_flush,
+                http1_writev,
+                http1_title_case_headers,
+                http1_preserve_header_case,
+                http1_only,
+                http2_only,
+                http2_initial_stream_window_size,
+                http2_initial_connection_window_size,
+                http2_adaptive_window,
+                http2_max_frame_size,
+                http2_max_concurrent_streams,
+                http2_max_send_buf_size,
+                http1_header_read_timeout
+            ]
+        );
+
+        let server = builder.serve(service::MakeService(Arc::new(service)));
+        executor::block_on(server).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    /// Drives a single, already-established connection to completion on the
+    /// calling thread, using this `Server`'s configured HTTP options.
+    ///
+    /// Unlike [`serve`](Server::serve)/[`serve_incoming`](Server::serve_incoming),
+    /// this doesn't bind a listener or spawn a worker pool: it's for callers
+    /// who did their own accepting (a custom listener, a socket-activated
+    /// fd, a stream that already went through a TLS handshake) and just
+    /// want astra's blocking [`Service`] model and HTTP tuning for the rest
+    /// of the connection's lifetime. `remote_addr` is the address of the
+    /// peer `conn` is connected to, made available to the service via
+    /// [`ConnInfo`].
+    ///
+    /// Build the `Server` with [`Server::new`] rather than
+    /// [`Server::bind`], since there's no address to resolve here.
+    pub fn serve_connection<C, S>(
+        self,
+        reactor: Reactor,
+        conn: C,
+        remote_addr: SocketAddr,
+        service: S,
+    ) -> io::Result<()>
+    where
+        C: Read + Write + Send + AsRawFd + 'static,
+        S: ConnService,
+    {
+        let io = crate::net::Stream::new(reactor, conn, self.idle_timeout)?;
+        let info = ConnInfo::new(remote_addr, None);
+
+        let http = options!(
+            self,
+            hyper::server::conn::Http::new(),
+            [
+                http1_keepalive,
+                http1_half_close,
+                http1_max_buf_size,
+                http1_pipeline_flush,
+                http1_writev,
+                http1_title_case_headers,
+                http1_preserve_header_case,
+                http1_only,
+                http2_only,
+                http2_initial_stream_window_size,
+                http2_initial_connection_window_size,
+                http2_adaptive_window,
+                http2_max_frame_size,
+                http2_max_concurrent_streams,
+                http2_max_send_buf_size,
+                http1_header_read_timeout
+            ]
+        );
+
+        let connection = http.serve_connection(io, service::Lazy::new(Arc::new(service), info));
+        executor::block_on(connection).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    /// Like [`serve`](Server::serve), but stops accepting new connections once
+    /// `handle` is [`shutdown`](Handle::shutdown), waits for in-flight
+    /// `Service::call` invocations to complete, and then returns.
+    ///
+    /// If `grace_period` is set and elapses before every connection has
+    /// finished draining, this returns anyway rather than waiting on them
+    /// indefinitely. The listener is dropped, but connections already
+    /// spawned onto the worker pool are not closed by this method.
+    ///
+    /// ```no_run
+    /// # use astra::{Server, Handle};
+    /// let handle = Handle::new();
+    ///
+    /// // Trigger `handle.shutdown()` from another thread, a signal handler, etc.
+    ///
+    /// Server::bind("localhost:3000")
+    ///     .serve_with_shutdown(handle, None, |_req| unimplemented!())
+    ///     .unwrap();
+    /// ```
+    pub fn serve_with_shutdown<S>(
+        self,
+        handle: Handle,
+        grace_period: impl Into<Option<Duration>>,
+        service: S,
+    ) -> io::Result<()>
+    where
+        S: ConnService,
+    {
+        let reactor = Reactor::new().expect("failed to create reactor");
+
+        let listener = TcpListener::bind(
+            reactor.clone(),
+            self.addr
+                .expect(
+                    "Server::serve_with_shutdown requires an address; construct with Server::bind, not Server::new",
+                )
+                .as_slice(),
+            self.idle_timeout,
+            Some(handle.signal()),
+        )
+        .expect("failed to bind listener");
+
+        let executor = executor::Executor::new(self.max_workers, self.worker_keep_alive);
+        let builder = hyper::Server::builder(listener).executor(executor);
+
+        let builder = options!(
+            self,
+            builder,
+            [
+                http1_keepalive,
+                http1_half_close,
+                http1_max_buf_size,
+                http1_pipeline_flush,
+                http1_writev,
+                http1_title_case_headers,
+                http1_preserve_header_case,
+                http1_only,
+                http2_only,
+                http2_initial_stream_window_size,
+                http2_initial_connection_window_size,
+                http2_adaptive_window,
+                http2_max_frame_size,
+                http2_max_concurrent_streams,
+                http2_max_send_buf_size,
+                http1_header_read_timeout
+            ]
+        );
+
+        let graceful: Pin<Box<dyn Future<Output = Result<(), hyper::Error>> + Send>> = Box::pin(
+            builder
+                .serve(service::MakeService(Arc::new(service)))
+                .with_graceful_shutdown(handle),
+        );
+
+        let grace_timer: Option<Pin<Box<dyn Future<Output = ()> + Send>>> = grace_period
+            .into()
+            .map(|duration| Box::pin(reactor.delay(duration)) as _);
+
+        let shutdown = shutdown::WithGracePeriod::new(graceful, grace_timer);
+
+        executor::block_on(shutdown).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
     /// Sets the maximum number of threads in the pool.
     ///
     /// By default, this is set to `num_cpus * 10`.
@@ -114,6 +550,29 @@ impl Server {
         self
     }
 
+    /// Sets a timeout for the client to send the full request headers after
+    /// the connection is established.
+    ///
+    /// If the headers haven't been fully received by the time the timeout
+    /// elapses, the connection is closed. This protects against
+    /// slowloris-style clients that trickle in a request one byte at a
+    /// time. Not set by default.
+    pub fn http1_header_read_timeout(mut self, val: Duration) -> Self {
+        self.http1_header_read_timeout = Some(val);
+        self
+    }
+
+    /// Sets how long a connection may sit idle, with no bytes read or
+    /// written, before it's closed.
+    ///
+    /// This is enforced by the reactor independently of the HTTP layer, so
+    /// it applies to connections stuck waiting between requests as well as
+    /// ones stuck mid-request. Not set by default.
+    pub fn idle_timeout(mut self, val: Duration) -> Self {
+        self.idle_timeout = Some(val);
+        self
+    }
+
     /// Sets whether to use keep-alive for HTTP/1 connections.
     ///
     /// Default is `true`.
@@ -281,12 +740,168 @@ impl Server {
     }
 }
 
+mod shutdown {
+    use super::*;
+
+    /// Races a graceful-shutdown future against an optional grace period.
+    ///
+    /// If `grace` fires first, this completes with `Ok(())` and `inner` (the
+    /// listener and its graceful-shutdown watch) is not polled any further,
+    /// rather than waiting on stragglers indefinitely. Connections hyper has
+    /// spawned onto the executor run as their own tasks and are not closed
+    /// here.
+    pub struct WithGracePeriod<F, T> {
+        inner: F,
+        grace: Option<T>,
+    }
+
+    impl<F, T> WithGracePeriod<F, T> {
+        pub fn new(inner: F, grace: Option<T>) -> Self {
+            WithGracePeriod { inner, grace }
+        }
+    }
+
+    impl<F, T> Future for WithGracePeriod<F, T>
+    where
+        F: Future<Output = Result<(), hyper::Error>> + Unpin,
+        T: Future<Output = ()> + Unpin,
+    {
+        type Output = Result<(), hyper::Error>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if let Poll::Ready(result) = Pin::new(&mut self.inner).poll(cx) {
+                return Poll::Ready(result);
+            }
+
+            if let Some(grace) = &mut self.grace {
+                if Pin::new(grace).poll(cx).is_ready() {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            Poll::Pending
+        }
+    }
+}
+
+mod incoming {
+    use super::*;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    /// Bridges an [`Accept`] implementor into hyper's own `Accept` trait by
+    /// wrapping each accepted connection in the same reactor-backed stream
+    /// [`TcpListener`] uses, so arbitrary blocking `Read + Write` streams
+    /// (e.g. a post-handshake TLS stream) get non-blocking readiness
+    /// notifications from the crate's [`Reactor`].
+    pub struct Incoming<A> {
+        reactor: Reactor,
+        acceptor: A,
+        idle_timeout: Option<Duration>,
+    }
+
+    impl<A> Incoming<A> {
+        pub fn new(reactor: Reactor, acceptor: A, idle_timeout: Option<Duration>) -> Self {
+            Incoming {
+                reactor,
+                acceptor,
+                idle_timeout,
+            }
+        }
+    }
+
+    impl<A> hyper::server::accept::Accept for Incoming<A>
+    where
+        A: Accept,
+        A::Conn: AsRawFd,
+    {
+        type Conn = IncomingStream<A::Conn>;
+        type Error = io::Error;
+
+        fn poll_accept(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+            match self.acceptor.accept() {
+                Ok(Some((conn, remote_addr))) => {
+                    let stream =
+                        crate::net::Stream::new(self.reactor.clone(), conn, self.idle_timeout);
+                    Poll::Ready(Some(stream.map(|stream| IncomingStream {
+                        stream,
+                        remote_addr,
+                    })))
+                }
+                Ok(None) => Poll::Ready(None),
+                Err(err) => Poll::Ready(Some(Err(err))),
+            }
+        }
+    }
+
+    /// The connection type handed to hyper for connections accepted through
+    /// [`Server::serve_incoming`]: the reactor-backed stream plus the peer
+    /// address, so it can still be captured in a [`ConnInfo`].
+    pub struct IncomingStream<C> {
+        stream: crate::net::Stream<C>,
+        remote_addr: SocketAddr,
+    }
+
+    impl<C> super::ConnAddr for IncomingStream<C> {
+        fn remote_addr(&self) -> SocketAddr {
+            self.remote_addr
+        }
+
+        fn local_addr(&self) -> Option<SocketAddr> {
+            None
+        }
+    }
+
+    impl<C> AsyncRead for IncomingStream<C>
+    where
+        crate::net::Stream<C>: AsyncRead,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            Pin::new(&mut this.stream).poll_read(cx, buf)
+        }
+    }
+
+    impl<C> AsyncWrite for IncomingStream<C>
+    where
+        crate::net::Stream<C>: AsyncWrite,
+    {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            Pin::new(&mut this.stream).poll_write(cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            Pin::new(&mut this.stream).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            Pin::new(&mut this.stream).poll_shutdown(cx)
+        }
+    }
+}
+
 mod service {
     use super::*;
 
     pub struct MakeService<S>(pub Arc<S>);
 
-    impl<T, S> hyper::service::Service<T> for MakeService<S> {
+    impl<T, S> hyper::service::Service<T> for MakeService<S>
+    where
+        T: ConnAddr,
+    {
         type Response = Lazy<S>;
         type Error = Infallible;
         type Future = Ready<Result<Lazy<S>, Infallible>>;
@@ -295,16 +910,26 @@ mod service {
             Poll::Ready(Ok(()))
         }
 
-        fn call(&mut self, _: T) -> Self::Future {
-            ready(Ok(Lazy(self.0.clone())))
+        fn call(&mut self, conn: T) -> Self::Future {
+            let info = ConnInfo::new(conn.remote_addr(), conn.local_addr());
+            ready(Ok(Lazy::new(self.0.clone(), info)))
         }
     }
 
-    pub struct Lazy<S>(Arc<S>);
+    pub struct Lazy<S> {
+        service: Arc<S>,
+        info: ConnInfo,
+    }
+
+    impl<S> Lazy<S> {
+        pub fn new(service: Arc<S>, info: ConnInfo) -> Self {
+            Lazy { service, info }
+        }
+    }
 
     impl<S> hyper::service::Service<Request<hyper::Body>> for Lazy<S>
     where
-        S: Service,
+        S: ConnService,
     {
         type Response = Response<hyper::Body>;
         type Error = Infallible;
@@ -315,21 +940,21 @@ mod service {
         }
 
         fn call(&mut self, req: Request<hyper::Body>) -> Self::Future {
-            Call(self.0.clone(), Some(req))
+            Call(self.service.clone(), self.info.clone(), Some(req))
         }
     }
 
-    pub struct Call<S>(Arc<S>, Option<Request<hyper::Body>>);
+    pub struct Call<S>(Arc<S>, ConnInfo, Option<Request<hyper::Body>>);
 
     impl<S> Future for Call<S>
     where
-        S: Service,
+        S: ConnService,
     {
         type Output = Result<Response<hyper::Body>, Infallible>;
 
         fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
-            let req = self.1.take().unwrap();
-            Poll::Ready(Ok(self.0.call(req)))
+            let req = self.2.take().unwrap();
+            Poll::Ready(Ok(self.0.call(req, &self.1)))
         }
     }
 }
@@ -351,3 +976,63 @@ macro_rules! options {
 }
 
 use options;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::task::{RawWaker, RawWakerVTable};
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+
+        fn raw_waker() -> RawWaker {
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+
+        unsafe { Waker::from_raw(raw_waker()) }
+    }
+
+    #[test]
+    fn handle_future_completes_on_shutdown() {
+        let mut handle = Handle::new();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Pending);
+
+        handle.shutdown();
+
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn handle_shutdown_is_idempotent() {
+        let handle = Handle::new();
+
+        handle.shutdown();
+        handle.shutdown();
+
+        assert!(handle.shutdown.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn handle_shutdown_wakes_pending_poll() {
+        let mut handle = Handle::new();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // Register interest, as `with_graceful_shutdown` would.
+        assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Pending);
+        assert!(handle.waker.lock().unwrap().is_some());
+
+        handle.shutdown();
+
+        // `shutdown` must have taken the waker to wake the pending task.
+        assert!(handle.waker.lock().unwrap().is_none());
+    }
+}